@@ -1,108 +1,325 @@
 use std::fs;
 use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
 use std::cmp::PartialEq;
 
+mod regex;
+
+use regex::Regex;
+
 type Line = u64;
 
 static USAGE_STR: &str = "\
 Usage:
-minigrep [-i] <QUERY> <FILE>";
+minigrep [-i] [-e] [-r] [-v] [-c] [-n] [-A N] [-B N] [-C N] <QUERY> [FILE...]
+With no FILE, or when FILE is -, read standard input.";
 
 #[derive(Debug)]
 pub struct Config<'a> {
     pub query: &'a str,
-    pub filename: &'a str,
+    pub filenames: Vec<&'a str>,
     pub case_sensitive: bool,
+    pub regex: bool,
+    pub recursive: bool,
+    pub invert_match: bool,
+    pub count_only: bool,
+    pub line_numbers: bool,
+    pub before_context: Line,
+    pub after_context: Line,
 }
 
 #[derive(Debug)]
 pub struct Match<'a> {
+    pub file: &'a str,
     pub line: Line,
     pub text: &'a str,
 }
 
 impl<'a> Config<'a> {
-    pub fn parse(args: &'a [String]) -> Result<Config,String> {
-        match args.len() {
-            3...4 => {
-                if args.len() == 3 {
-                    Ok(Config { query: &args[1], filename: &args[2], case_sensitive: true })
-                } else if &args[1] == "-i" {
-                    Ok(Config { query: &args[2], filename: &args[3], case_sensitive: false })
-                } else {
-                    Err(format!("First argument '{}' is not a valid option\n{}", &args[1], USAGE_STR))
-                }
-            },
-            n if n < 3 => Err(format!("Not enough arguments\n{}", USAGE_STR)),
-            _ => Err(format!("Too many arguments\n{}", USAGE_STR)),
+    /// Parses `args` (the full `env::args`-style vector, including the
+    /// program name at index 0) into a `Config`. Options are scanned off the
+    /// front as an iterator, exactly like the external `minigrep` clones
+    /// consume `env::Args`: any run of tokens starting with `-` is read as a
+    /// flag until the first non-option token, or until a bare `--` forces an
+    /// early end to option scanning. Whatever remains is bound to the query
+    /// and, optionally, one or more file/directory arguments.
+    pub fn parse(args: &'a [String]) -> Result<Config<'a>,String> {
+        let mut case_sensitive = true;
+        let mut regex = false;
+        let mut recursive = false;
+        let mut invert_match = false;
+        let mut count_only = false;
+        let mut line_numbers = false;
+        let mut before_context: Line = 0;
+        let mut after_context: Line = 0;
+
+        let mut args = args.iter().peekable();
+        args.next(); // program name
+
+        while let Some(arg) = args.peek().map(|s| s.as_str()) {
+            if arg == "--" {
+                args.next();
+                break;
+            }
+            if !arg.starts_with('-') {
+                break;
+            }
+            match arg {
+                "-i" => { case_sensitive = false; args.next(); },
+                "-e" => { regex = true; args.next(); },
+                "-r" => { recursive = true; args.next(); },
+                "-v" => { invert_match = true; args.next(); },
+                "-c" => { count_only = true; args.next(); },
+                "-n" => { line_numbers = true; args.next(); },
+                "-A" | "-B" | "-C" => {
+                    args.next();
+                    let n = Self::parse_context_arg(arg, args.next())?;
+                    match arg {
+                        "-A" => after_context = n,
+                        "-B" => before_context = n,
+                        _ => { before_context = n; after_context = n; },
+                    }
+                },
+                flag => return Err(format!("'{}' is not a valid option\n{}", flag, USAGE_STR)),
+            }
+        }
+
+        let rest: Vec<&str> = args.map(String::as_str).collect();
+        if rest.is_empty() {
+            return Err(format!("Not enough arguments\n{}", USAGE_STR));
         }
+        let query = rest[0];
+        let filenames = if rest.len() < 2 { vec!["-"] } else { rest[1..].to_vec() };
+        Ok(Config {
+            query, filenames, case_sensitive, regex, recursive,
+            invert_match, count_only, line_numbers, before_context, after_context,
+        })
+    }
+
+    fn parse_context_arg(flag: &str, value: Option<&'a String>) -> Result<Line,String> {
+        let value = value.ok_or_else(|| format!("Option '{}' requires a numeric argument\n{}", flag, USAGE_STR))?;
+        value.parse().map_err(|_| format!("Option '{}' requires a numeric argument\n{}", flag, USAGE_STR))
     }
 }
 
 impl<'a> Match<'a> {
-    pub fn new(line: Line, text: &str) -> Match {
-        Match { line, text }
+    pub fn new(file: &'a str, line: Line, text: &'a str) -> Match<'a> {
+        Match { file, line, text }
     }
 }
 
 impl<'a> PartialEq for Match<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.line == other.line && self.text == other.text
+        self.file == other.file && self.line == other.line && self.text == other.text
     }
 }
 
 impl<'a> fmt::Display for Match<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.text)
+        write!(f, "{}:{}", self.file, self.text)
     }
 }
 
+/// Reads `filename`'s contents, or standard input when `filename` is `-`.
 pub fn read_file(filename: &str) -> Result<String,String> {
+    if filename == "-" {
+        return read_stdin();
+    }
     match fs::read_to_string(filename) {
         Ok(s) => Ok(s),
         Err(e) => Err(format!("Error reading \"{}\": {}", filename, e)),
     }
 }
 
-pub fn search_case_sensitive<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+fn read_stdin() -> Result<String,String> {
+    let mut buffer = String::new();
+    io::stdin().read_to_string(&mut buffer)
+        .map_err(|e| format!("Error reading standard input: {}", e))?;
+    Ok(buffer)
+}
+
+/// Expands `filenames` into a flat, deterministically ordered list of
+/// regular files, descending into directories depth-first (sorted by
+/// entry name) when `recursive` is set. `-` passes through untouched to be
+/// read from standard input.
+pub fn collect_files(filenames: &[&str], recursive: bool) -> Result<Vec<String>,String> {
+    let mut files = Vec::new();
+    for filename in filenames {
+        if *filename == "-" {
+            files.push(String::from("-"));
+        } else {
+            walk(Path::new(filename), recursive, &mut files)?;
+        }
+    }
+    Ok(files)
+}
+
+fn walk(path: &Path, recursive: bool, files: &mut Vec<String>) -> Result<(),String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Error reading \"{}\": {}", path.display(), e))?;
+    if metadata.is_dir() {
+        if !recursive {
+            return Err(format!("\"{}\" is a directory (use -r to search recursively)", path.display()));
+        }
+        let mut entries: Vec<_> = fs::read_dir(path)
+            .map_err(|e| format!("Error reading \"{}\": {}", path.display(), e))?
+            .collect::<Result<Vec<_>,_>>()
+            .map_err(|e| format!("Error reading \"{}\": {}", path.display(), e))?;
+        entries.sort_by_key(|entry| entry.path());
+        for entry in entries {
+            walk(&entry.path(), recursive, files)?;
+        }
+        Ok(())
+    } else {
+        files.push(path.to_string_lossy().into_owned());
+        Ok(())
+    }
+}
+
+pub fn search_case_sensitive<'a>(query: &str, file: &'a str, contents: &'a str, invert: bool) -> Vec<Match<'a>> {
     let mut results = Vec::new();
     let mut line: Line = 1;
     for text in contents.lines() {
-        if text.contains(query) {
-            results.push(Match::new(line, text));
+        if text.contains(query) != invert {
+            results.push(Match::new(file, line, text));
         }
         line += 1;
     }
     results
 }
 
-pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+pub fn search_case_insensitive<'a>(query: &str, file: &'a str, contents: &'a str, invert: bool) -> Vec<Match<'a>> {
     let query_lower = query.to_lowercase();
     let mut results = Vec::new();
     let mut line: Line = 1;
     for text in contents.lines() {
-        if text.to_lowercase().contains(&query_lower) {
-            results.push(Match::new(line, text));
+        if text.to_lowercase().contains(&query_lower) != invert {
+            results.push(Match::new(file, line, text));
         }
         line += 1;
     }
     results
 }
 
-pub fn search<'a>(query: &str, contents: &'a str, case_sensitive: bool) -> Vec<Match<'a>> {
+/// Searches `contents` for lines matching (or, with `invert`, NOT matching)
+/// the regular expression `query`. When `case_sensitive` is false, both the pattern
+/// and the input are folded to lowercase before the program is run.
+pub fn search_regex<'a>(query: &str, file: &'a str, contents: &'a str, case_sensitive: bool, invert: bool) -> Result<Vec<Match<'a>>,String> {
+    let re = Regex::compile(query, !case_sensitive)?;
+    let mut results = Vec::new();
+    let mut line: Line = 1;
+    for text in contents.lines() {
+        let matched = if case_sensitive {
+            re.is_match(text)
+        } else {
+            re.is_match(&text.to_lowercase())
+        };
+        if matched != invert {
+            results.push(Match::new(file, line, text));
+        }
+        line += 1;
+    }
+    Ok(results)
+}
+
+pub fn search<'a>(query: &str, file: &'a str, contents: &'a str, case_sensitive: bool, invert: bool) -> Vec<Match<'a>> {
     if case_sensitive {
-        search_case_sensitive(query, contents)
+        search_case_sensitive(query, file, contents, invert)
     } else {
-        search_case_insensitive(query, contents)
+        search_case_insensitive(query, file, contents, invert)
     }
 }
 
+/// Assembles the grep-style context block around `matched_lines` in `file`:
+/// each match is padded with up to `before`/`after` neighbouring lines,
+/// overlapping or adjacent windows are merged, and a `--` separator is
+/// inserted between windows that remain non-adjacent. Every emitted line is
+/// prefixed with `file` (and, when `line_numbers` is set, its line number),
+/// the same as the non-context output, so context output from different
+/// files stays visually distinguishable and `-n` keeps working with `-A`/
+/// `-B`/`-C`.
+pub fn with_context(file: &str, contents: &str, matched_lines: &[Line], before: Line, after: Line, line_numbers: bool) -> Vec<String> {
+    let lines: Vec<&str> = contents.lines().collect();
+    let total = lines.len() as Line;
+    if lines.is_empty() || matched_lines.is_empty() {
+        return Vec::new();
+    }
+
+    let mut windows: Vec<(Line, Line)> = matched_lines
+        .iter()
+        .map(|&matched| {
+            let start = if matched > before { matched - before } else { 1 };
+            let end = if matched + after < total { matched + after } else { total };
+            (start, end)
+        })
+        .collect();
+    windows.sort();
+
+    let mut merged: Vec<(Line, Line)> = Vec::new();
+    for window in windows {
+        match merged.last_mut() {
+            Some(last) if window.0 <= last.1 + 1 => {
+                if window.1 > last.1 {
+                    last.1 = window.1;
+                }
+            },
+            _ => merged.push(window),
+        }
+    }
+
+    let mut output = Vec::new();
+    for (start, end) in merged {
+        if !output.is_empty() {
+            output.push("--".to_string());
+        }
+        for line_no in start..=end {
+            let text = lines[(line_no - 1) as usize];
+            if line_numbers {
+                output.push(format!("{}:{}:{}", file, line_no, text));
+            } else {
+                output.push(format!("{}:{}", file, text));
+            }
+        }
+    }
+    output
+}
+
 pub fn run(args: Vec<String>) -> Result<(),String> {
     let config = Config::parse(&args)?;
-    let contents = read_file(config.filename)?;
-    let matches = search(config.query, &contents, config.case_sensitive);
-    for line in matches {
-        println!("{}", line);
+    let files = collect_files(&config.filenames, config.recursive)?;
+    let want_context = config.before_context > 0 || config.after_context > 0;
+    let mut printed_context_block = false;
+    for file in &files {
+        let contents = read_file(file)?;
+        let matches = if config.regex {
+            search_regex(config.query, file, &contents, config.case_sensitive, config.invert_match)?
+        } else {
+            search(config.query, file, &contents, config.case_sensitive, config.invert_match)
+        };
+        if config.count_only {
+            println!("{}:{}", file, matches.len());
+        } else if want_context {
+            let matched_lines: Vec<Line> = matches.iter().map(|m| m.line).collect();
+            let block = with_context(file, &contents, &matched_lines, config.before_context, config.after_context, config.line_numbers);
+            if !block.is_empty() {
+                if printed_context_block {
+                    println!("--");
+                }
+                for line in &block {
+                    println!("{}", line);
+                }
+                printed_context_block = true;
+            }
+        } else if config.line_numbers {
+            for m in matches {
+                println!("{}:{}:{}", m.file, m.line, m.text);
+            }
+        } else {
+            for m in matches {
+                println!("{}", m);
+            }
+        }
     }
     Ok(())
 }
@@ -121,9 +338,9 @@ Pick three.";
 
         assert_eq!(
             vec![
-                Match { line: 2, text: "safe, fast, productive." }
+                Match { file: "poem.txt", line: 2, text: "safe, fast, productive." }
             ],
-            search_case_sensitive(query, contents)
+            search_case_sensitive(query, "poem.txt", contents, false)
         );
     }
 
@@ -138,10 +355,258 @@ Trust me.";
 
         assert_eq!(
             vec![
-                Match { line: 1, text: "Rust:" },
-                Match { line: 4, text: "Trust me." }
+                Match { file: "poem.txt", line: 1, text: "Rust:" },
+                Match { file: "poem.txt", line: 4, text: "Trust me." }
+            ],
+            search_case_insensitive(query, "poem.txt", contents, false)
+        );
+    }
+
+    #[test]
+    fn regex_mode() {
+        let query = "^fn [a-z]+";
+        let contents = "\
+fn main() {
+pub fn helper() {
+}";
+
+        assert_eq!(
+            vec![Match { file: "main.rs", line: 1, text: "fn main() {" }],
+            search_regex(query, "main.rs", contents, true, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_with_regex_flag() {
+        let args: Vec<String> = vec!["minigrep", "-e", "colou?r", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert_eq!(config.query, "colou?r");
+        assert_eq!(config.filenames, vec!["poem.txt"]);
+        assert!(config.regex);
+        assert!(config.case_sensitive);
+    }
+
+    #[test]
+    fn parse_multiple_filenames() {
+        let args: Vec<String> = vec!["minigrep", "-r", "duct", "a.txt", "b.txt", "dir/"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert!(config.recursive);
+        assert_eq!(config.filenames, vec!["a.txt", "b.txt", "dir/"]);
+    }
+
+    #[test]
+    fn parse_without_filename_defaults_to_stdin() {
+        let args: Vec<String> = vec!["minigrep", "duct"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert_eq!(config.filenames, vec!["-"]);
+    }
+
+    #[test]
+    fn parse_dash_filename_is_explicit_stdin() {
+        let args: Vec<String> = vec!["minigrep", "duct", "-"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert_eq!(config.filenames, vec!["-"]);
+    }
+
+    #[test]
+    fn collect_files_passes_dash_through_without_touching_the_filesystem() {
+        let files = collect_files(&["-"], false).unwrap();
+        assert_eq!(files, vec!["-".to_string()]);
+    }
+
+    #[test]
+    fn match_display_includes_path_but_not_line_by_default() {
+        let m = Match::new("src/lib.rs", 42, "    let x = 1;");
+        assert_eq!("src/lib.rs:    let x = 1;", format!("{}", m));
+    }
+
+    #[test]
+    fn invert_match_keeps_non_matching_lines() {
+        let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.";
+
+        assert_eq!(
+            vec![
+                Match { file: "poem.txt", line: 1, text: "Rust:" },
+                Match { file: "poem.txt", line: 3, text: "Pick three." },
+            ],
+            search_case_sensitive("duct", "poem.txt", contents, true)
+        );
+    }
+
+    #[test]
+    fn parse_combined_flags_in_any_order() {
+        let args: Vec<String> = vec!["minigrep", "-n", "-c", "-v", "-i", "duct", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert!(config.line_numbers);
+        assert!(config.count_only);
+        assert!(config.invert_match);
+        assert!(!config.case_sensitive);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_flag() {
+        let args: Vec<String> = vec!["minigrep", "-z", "duct", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert!(Config::parse(&args).is_err());
+    }
+
+    #[test]
+    fn parse_double_dash_ends_option_scanning() {
+        let args: Vec<String> = vec!["minigrep", "--", "-v", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert_eq!(config.query, "-v");
+        assert_eq!(config.filenames, vec!["poem.txt"]);
+        assert!(!config.invert_match);
+    }
+
+    #[test]
+    fn recursive_walk_is_sorted_and_depth_first() {
+        let root = std::env::temp_dir().join("minigrep_test_recursive_walk");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("sub").join("c.txt"), "c").unwrap();
+
+        let root_str = root.to_str().unwrap();
+        let files = collect_files(&[root_str], true).unwrap();
+
+        assert_eq!(
+            vec![
+                root.join("a.txt").to_string_lossy().into_owned(),
+                root.join("b.txt").to_string_lossy().into_owned(),
+                root.join("sub").join("c.txt").to_string_lossy().into_owned(),
             ],
-            search_case_insensitive(query, contents)
+            files
         );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn collect_files_rejects_directory_without_recursive_flag() {
+        let root = std::env::temp_dir().join("minigrep_test_non_recursive");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        assert!(collect_files(&[root.to_str().unwrap()], false).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn parse_context_flags() {
+        let args: Vec<String> = vec!["minigrep", "-C", "2", "duct", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let config = Config::parse(&args).unwrap();
+        assert_eq!(config.before_context, 2);
+        assert_eq!(config.after_context, 2);
+    }
+
+    #[test]
+    fn parse_context_flag_missing_argument_is_an_error() {
+        let args: Vec<String> = vec!["minigrep", "-A", "duct", "poem.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert!(Config::parse(&args).is_err());
+    }
+
+    #[test]
+    fn with_context_merges_overlapping_windows() {
+        let contents = "\
+1
+2
+3
+4
+5
+6
+7
+8
+9";
+        // matches on lines 2 and 4, with 1 line of context each: windows
+        // [1,3] and [3,5] overlap and should merge into a single block.
+        assert_eq!(
+            vec!["f:1", "f:2", "f:3", "f:4", "f:5"],
+            with_context("f", contents, &[2, 4], 1, 1, false)
+        );
+    }
+
+    #[test]
+    fn with_context_separates_distant_groups() {
+        let contents = "\
+1
+2
+3
+4
+5
+6
+7
+8
+9";
+        // matches on lines 1 and 9, with no context: two isolated lines far
+        // apart should be separated by a "--" marker.
+        assert_eq!(
+            vec!["f:1", "--", "f:9"],
+            with_context("f", contents, &[1, 9], 0, 0, false)
+        );
+    }
+
+    #[test]
+    fn with_context_prefixes_every_line_with_the_file() {
+        let contents = "\
+a
+b
+c";
+        assert_eq!(
+            vec!["notes.txt:a", "notes.txt:b", "notes.txt:c"],
+            with_context("notes.txt", contents, &[2], 1, 1, false)
+        );
+    }
+
+    #[test]
+    fn with_context_includes_line_numbers_when_requested() {
+        let contents = "\
+a
+b
+c";
+        assert_eq!(
+            vec!["notes.txt:1:a", "notes.txt:2:b", "notes.txt:3:c"],
+            with_context("notes.txt", contents, &[2], 1, 1, true)
+        );
+    }
+
+    #[test]
+    fn with_context_returns_nothing_for_a_file_with_no_matches() {
+        let contents = "\
+a
+b
+c";
+        assert!(with_context("notes.txt", contents, &[], 1, 1, false).is_empty());
     }
 }
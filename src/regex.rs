@@ -0,0 +1,249 @@
+//! A small, dependency-free regular expression engine.
+//!
+//! Patterns are compiled into a Thompson-style NFA program (`Char`, `Any`,
+//! `Class`, `Split`, `Jmp`, `Match`) and executed with a Pike VM: the set of
+//! active program counters is advanced one input character at a time, and a
+//! line matches as soon as any thread reaches `Match`.
+//!
+//! Supported syntax: `.`, `*`, `+`, `?`, character classes `[...]`/`[^...]`
+//! with ranges (`a-z`), `\` escapes, and the anchors `^`/`$`.
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<(char, char)>, bool),
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+    MatchEnd,
+}
+
+#[derive(Debug)]
+pub struct Regex {
+    prog: Vec<Inst>,
+    anchored_start: bool,
+}
+
+impl Regex {
+    /// Compiles `pattern` into a runnable program. When `case_insensitive`
+    /// is set, the pattern is folded to lowercase so it can be matched
+    /// against already-folded input.
+    pub fn compile(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+        let folded = if case_insensitive {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        let mut chars: Vec<char> = folded.chars().collect();
+        let anchored_start = chars.first() == Some(&'^');
+        if anchored_start {
+            chars.remove(0);
+        }
+        let anchored_end = chars.last() == Some(&'$');
+        if anchored_end {
+            chars.pop();
+        }
+        let mut prog = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let (atom, consumed) = parse_atom(&chars[i..])?;
+            i += consumed;
+            match chars.get(i) {
+                Some('*') => {
+                    i += 1;
+                    let split = prog.len();
+                    prog.push(Inst::Split(0, 0));
+                    let body = prog.len();
+                    prog.extend(atom);
+                    prog.push(Inst::Jmp(split));
+                    let after = prog.len();
+                    prog[split] = Inst::Split(body, after);
+                }
+                Some('+') => {
+                    i += 1;
+                    let body = prog.len();
+                    prog.extend(atom);
+                    let split = prog.len();
+                    prog.push(Inst::Split(0, 0));
+                    let after = prog.len();
+                    prog[split] = Inst::Split(body, after);
+                }
+                Some('?') => {
+                    i += 1;
+                    let split = prog.len();
+                    prog.push(Inst::Split(0, 0));
+                    let body = prog.len();
+                    prog.extend(atom);
+                    let after = prog.len();
+                    prog[split] = Inst::Split(body, after);
+                }
+                _ => prog.extend(atom),
+            }
+        }
+        prog.push(if anchored_end { Inst::MatchEnd } else { Inst::Match });
+        Ok(Regex { prog, anchored_start })
+    }
+
+    /// Returns true if the pattern matches anywhere in `text` (unless
+    /// anchored with `^`, in which case only the start of `text` is tried).
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        for start in 0..=chars.len() {
+            if self.run_from(&chars, start) {
+                return true;
+            }
+            if self.anchored_start {
+                break;
+            }
+        }
+        false
+    }
+
+    fn run_from(&self, chars: &[char], start: usize) -> bool {
+        let mut clist = Vec::new();
+        let mut seen = vec![false; self.prog.len()];
+        add_thread(&self.prog, &mut clist, &mut seen, 0);
+        let mut pos = start;
+        loop {
+            if clist.is_empty() {
+                return false;
+            }
+            let c = chars.get(pos).copied();
+            let mut nlist = Vec::new();
+            let mut nseen = vec![false; self.prog.len()];
+            for pc in &clist {
+                match &self.prog[*pc] {
+                    Inst::Char(ch) if c == Some(*ch) => {
+                        add_thread(&self.prog, &mut nlist, &mut nseen, pc + 1)
+                    }
+                    Inst::Any if c.is_some() => {
+                        add_thread(&self.prog, &mut nlist, &mut nseen, pc + 1)
+                    }
+                    Inst::Class(ranges, negate) => {
+                        if let Some(ch) = c {
+                            let in_class = ranges.iter().any(|(lo, hi)| ch >= *lo && ch <= *hi);
+                            if in_class != *negate {
+                                add_thread(&self.prog, &mut nlist, &mut nseen, pc + 1);
+                            }
+                        }
+                    }
+                    Inst::Match => return true,
+                    Inst::MatchEnd if c.is_none() => return true,
+                    _ => {}
+                }
+            }
+            if c.is_none() {
+                return false;
+            }
+            clist = nlist;
+            pos += 1;
+        }
+    }
+}
+
+fn add_thread(prog: &[Inst], list: &mut Vec<usize>, seen: &mut [bool], pc: usize) {
+    if seen[pc] {
+        return;
+    }
+    seen[pc] = true;
+    match &prog[pc] {
+        Inst::Split(a, b) => {
+            add_thread(prog, list, seen, *a);
+            add_thread(prog, list, seen, *b);
+        }
+        Inst::Jmp(a) => add_thread(prog, list, seen, *a),
+        _ => list.push(pc),
+    }
+}
+
+fn parse_atom(chars: &[char]) -> Result<(Vec<Inst>, usize), String> {
+    match chars.first() {
+        None => Err("Unexpected end of pattern".to_string()),
+        Some('.') => Ok((vec![Inst::Any], 1)),
+        Some('\\') => match chars.get(1) {
+            Some(c) => Ok((vec![Inst::Char(*c)], 2)),
+            None => Err("Trailing '\\' in pattern".to_string()),
+        },
+        Some('[') => parse_class(chars),
+        Some(c) => Ok((vec![Inst::Char(*c)], 1)),
+    }
+}
+
+fn parse_class(chars: &[char]) -> Result<(Vec<Inst>, usize), String> {
+    let mut i = 1;
+    let negate = chars.get(i) == Some(&'^');
+    if negate {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    while chars.get(i) != Some(&']') {
+        let lo = *chars
+            .get(i)
+            .ok_or_else(|| "Unterminated character class".to_string())?;
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+            ranges.push((lo, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((lo, lo));
+            i += 1;
+        }
+    }
+    i += 1;
+    Ok((vec![Inst::Class(ranges, negate)], i))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal() {
+        let re = Regex::compile("duct", false).unwrap();
+        assert!(re.is_match("productive"));
+        assert!(!re.is_match("producer"));
+    }
+
+    #[test]
+    fn dot_and_quantifiers() {
+        let re = Regex::compile("colou?r", false).unwrap();
+        assert!(re.is_match("color"));
+        assert!(re.is_match("colour"));
+        assert!(!re.is_match("colouur"));
+
+        let re = Regex::compile("a.*c", false).unwrap();
+        assert!(re.is_match("a123c"));
+        assert!(!re.is_match("ab"));
+    }
+
+    #[test]
+    fn escaped_metacharacter() {
+        let re = Regex::compile("a\\.b", false).unwrap();
+        assert!(re.is_match("a.b"));
+        assert!(!re.is_match("axb"));
+    }
+
+    #[test]
+    fn character_class() {
+        let re = Regex::compile("[a-c]+", false).unwrap();
+        assert!(re.is_match("xbby"));
+        assert!(!re.is_match("xyz"));
+    }
+
+    #[test]
+    fn anchors() {
+        let re = Regex::compile("^fn", false).unwrap();
+        assert!(re.is_match("fn main()"));
+        assert!(!re.is_match("pub fn main()"));
+
+        let re = Regex::compile("done$", false).unwrap();
+        assert!(re.is_match("all done"));
+        assert!(!re.is_match("done already"));
+    }
+
+    #[test]
+    fn case_insensitive_folds_pattern() {
+        let re = Regex::compile("RuSt", true).unwrap();
+        assert!(re.is_match("rust"));
+    }
+}